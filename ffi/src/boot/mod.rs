@@ -0,0 +1,281 @@
+//! Rootfs boot pipeline: loop-mount + overlay + pivot_root.
+//!
+//! This is the machinery behind [`crate::vm::agent_launch_vm`]'s
+//! `rootfs_image` field, ported from the mount/overlay/switch_root
+//! approach used by rumia's `mount.rs`, `swap.rs`, and `swroot.rs`.
+//!
+//! `pivot_root` rewrites the calling process's filesystem root, and on
+//! Linux that root is shared by every thread in the process unless the
+//! thread first opts out with `unshare(CLONE_FS)`. Rather than relying on
+//! that, [`boot_rootfs`] forks a dedicated child process to do the
+//! `unshare`/mount/`pivot_root` work in, so the host process embedding
+//! this FFI never has its own root swapped out from under it. The child
+//! becomes the VM's standing process: it blocks waiting for `SIGTERM`
+//! (sent by [`teardown`]), then unwinds its own mounts and exits.
+
+pub(crate) mod mount;
+mod swap;
+mod swroot;
+
+use std::io::{Read, Write};
+use std::os::raw::c_int;
+use std::os::unix::io::FromRawFd;
+use std::path::{Path, PathBuf};
+
+pub use mount::MountedLayer;
+pub use swap::SwapConfig;
+
+/// Distinct failure stages of the rootfs boot pipeline, each surfaced to
+/// the FFI boundary as its own [`crate::error::AgentError`] code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootError {
+    NamespaceFailed,
+    LoopAttachFailed,
+    OverlayMountFailed,
+    PseudoFsMountFailed,
+    PivotRootFailed,
+    /// The boot child exited (or could never be consulted) before
+    /// reporting an outcome.
+    ChildDied,
+}
+
+impl BootError {
+    fn to_wire(self) -> i32 {
+        match self {
+            BootError::NamespaceFailed => 0,
+            BootError::LoopAttachFailed => 1,
+            BootError::OverlayMountFailed => 2,
+            BootError::PseudoFsMountFailed => 3,
+            BootError::PivotRootFailed => 4,
+            BootError::ChildDied => 5,
+        }
+    }
+
+    fn from_wire(code: i32) -> BootError {
+        match code {
+            0 => BootError::NamespaceFailed,
+            1 => BootError::LoopAttachFailed,
+            2 => BootError::OverlayMountFailed,
+            3 => BootError::PseudoFsMountFailed,
+            4 => BootError::PivotRootFailed,
+            _ => BootError::ChildDied,
+        }
+    }
+}
+
+/// Why the boot child failed: either a named [`BootError`] stage, or the
+/// boot sequence itself failing at a given step index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BootFailure {
+    Stage(BootError),
+    SequenceStep(usize),
+}
+
+/// Everything that was brought online while booting a VM's rootfs, kept
+/// around so it can be reported back via `agent_vm_status` and torn down
+/// by [`teardown`].
+#[derive(Debug, Default, Clone)]
+pub struct BootState {
+    pub layers: Vec<MountedLayer>,
+    pub merged_root: Option<PathBuf>,
+    pub swap_active: bool,
+    pub(crate) child_pid: Option<libc::pid_t>,
+}
+
+/// Brings a microVM's rootfs online by forking a dedicated boot child:
+///
+/// 1. Unshares the child into a fresh mount namespace.
+/// 2. Loop-mounts `rootfs_image` read-only as the overlay lowerdir.
+/// 3. Creates a tmpfs-backed upperdir+workdir capped at `memory_mib`.
+/// 4. Mounts an `overlay` filesystem merging lower and upper.
+/// 5. Bind-mounts `/proc`, `/sys`, `/dev` into the merged tree.
+/// 6. Optionally activates a swapfile, per `swap_config` if given or else
+///    falling back to activating one when memory is constrained
+///    (best-effort — a failure here doesn't fail the boot).
+/// 7. `pivot_root`s into the merged tree (falling back to `switch_root`
+///    semantics if the kernel rejects `pivot_root`) and unmounts the old
+///    root.
+/// 8. Runs `run_boot_sequence` against the new root.
+///
+/// The child then reports its outcome back over a pipe and, on success,
+/// blocks until [`teardown`] signals it to unwind and exit. The calling
+/// (host) process's own mount namespace and root are never touched.
+pub fn boot_rootfs(
+    rootfs_image: &Path,
+    work_root: &Path,
+    memory_mib: u32,
+    swap_config: Option<SwapConfig>,
+    run_boot_sequence: impl FnOnce() -> Result<(), usize>,
+) -> Result<BootState, BootFailure> {
+    let mut fds = [0 as c_int; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(BootFailure::Stage(BootError::NamespaceFailed));
+    }
+    let (read_fd, write_fd) = (fds[0], fds[1]);
+
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        unsafe {
+            libc::close(read_fd);
+            libc::close(write_fd);
+        }
+        return Err(BootFailure::Stage(BootError::NamespaceFailed));
+    }
+
+    if pid == 0 {
+        unsafe { libc::close(read_fd) };
+        run_child(
+            rootfs_image,
+            work_root,
+            memory_mib,
+            swap_config,
+            run_boot_sequence,
+            write_fd,
+        );
+    }
+
+    unsafe { libc::close(write_fd) };
+    let outcome = read_outcome(read_fd);
+
+    match outcome {
+        Some((0, swap_active)) => Ok(BootState {
+            layers: vec![mount::layer_paths(work_root)],
+            merged_root: Some(work_root.join("merged")),
+            swap_active: swap_active != 0,
+            child_pid: Some(pid),
+        }),
+        Some((1, code)) => {
+            reap(pid);
+            Err(BootFailure::Stage(BootError::from_wire(code)))
+        }
+        Some((2, index)) => {
+            reap(pid);
+            Err(BootFailure::SequenceStep(index as usize))
+        }
+        _ => {
+            reap(pid);
+            Err(BootFailure::Stage(BootError::ChildDied))
+        }
+    }
+}
+
+/// Runs the boot pipeline in the forked child and never returns: it
+/// either reports a failure and exits, or reports success and blocks
+/// waiting to be torn down.
+fn run_child(
+    rootfs_image: &Path,
+    work_root: &Path,
+    memory_mib: u32,
+    swap_config: Option<SwapConfig>,
+    run_boot_sequence: impl FnOnce() -> Result<(), usize>,
+    write_fd: c_int,
+) -> ! {
+    // Block SIGTERM up front so a stop request can only be observed via
+    // `sigwait` once boot has actually finished and there's something to
+    // unwind.
+    let term_set = unsafe {
+        let mut set: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, libc::SIGTERM);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+        set
+    };
+
+    let mut out = unsafe { std::fs::File::from_raw_fd(write_fd) };
+
+    let result = (|| -> Result<(MountedLayer, bool), BootFailure> {
+        swroot::unshare_mount_namespace().map_err(BootFailure::Stage)?;
+
+        let layer = mount::mount_overlay_rootfs(rootfs_image, work_root, memory_mib)
+            .map_err(BootFailure::Stage)?;
+
+        if let Err(err) = mount::bind_pseudo_filesystems(&layer.merged) {
+            mount::unmount_layers(std::slice::from_ref(&layer));
+            return Err(BootFailure::Stage(err));
+        }
+
+        let swap_active = swap::activate_swap_if_needed(&layer.merged, memory_mib, swap_config);
+
+        if let Err(err) = swroot::pivot_into_rootfs(&layer.merged) {
+            if swap_active {
+                swap::deactivate_swap(&layer.merged);
+            }
+            mount::unmount_layers(std::slice::from_ref(&layer));
+            return Err(BootFailure::Stage(err));
+        }
+
+        run_boot_sequence().map_err(BootFailure::SequenceStep)?;
+
+        Ok((layer, swap_active))
+    })();
+
+    match result {
+        Ok((layer, swap_active)) => {
+            let _ = write_message(&mut out, 0, swap_active as i32);
+            drop(out);
+            wait_for_stop_and_cleanup(term_set, &layer, swap_active);
+        }
+        Err(BootFailure::Stage(err)) => {
+            let _ = write_message(&mut out, 1, err.to_wire());
+            drop(out);
+            unsafe { libc::_exit(1) };
+        }
+        Err(BootFailure::SequenceStep(index)) => {
+            let _ = write_message(&mut out, 2, index as i32);
+            drop(out);
+            unsafe { libc::_exit(1) };
+        }
+    }
+}
+
+/// Blocks until the host asks the boot child to stop, then unwinds its
+/// mounts and exits. Never returns.
+fn wait_for_stop_and_cleanup(term_set: libc::sigset_t, layer: &MountedLayer, swap_active: bool) -> ! {
+    let mut signal: c_int = 0;
+    unsafe {
+        libc::sigwait(&term_set, &mut signal);
+    }
+    if swap_active {
+        swap::deactivate_swap(&layer.merged);
+    }
+    mount::unmount_layers(std::slice::from_ref(layer));
+    unsafe { libc::_exit(0) };
+}
+
+fn write_message(out: &mut std::fs::File, tag: i32, payload: i32) -> std::io::Result<()> {
+    let mut buf = [0u8; 8];
+    buf[0..4].copy_from_slice(&tag.to_le_bytes());
+    buf[4..8].copy_from_slice(&payload.to_le_bytes());
+    out.write_all(&buf)
+}
+
+/// Reads the boot child's `(tag, payload)` outcome, returning `None` if it
+/// died before writing one.
+fn read_outcome(read_fd: c_int) -> Option<(i32, i32)> {
+    let mut file = unsafe { std::fs::File::from_raw_fd(read_fd) };
+    let mut buf = [0u8; 8];
+    file.read_exact(&mut buf).ok()?;
+    let tag = i32::from_le_bytes(buf[0..4].try_into().unwrap());
+    let payload = i32::from_le_bytes(buf[4..8].try_into().unwrap());
+    Some((tag, payload))
+}
+
+fn reap(pid: libc::pid_t) {
+    let mut status: c_int = 0;
+    unsafe {
+        libc::waitpid(pid, &mut status, 0);
+    }
+}
+
+/// Tears down a [`BootState`] by asking its boot child to stop and
+/// reaping it. The child unmounts its own layers (and the kernel reclaims
+/// anything left over once its private mount namespace's last reference
+/// drops) before it exits.
+pub fn teardown(state: &BootState) {
+    if let Some(pid) = state.child_pid {
+        unsafe {
+            libc::kill(pid, libc::SIGTERM);
+        }
+        reap(pid);
+    }
+}
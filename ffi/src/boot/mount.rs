@@ -0,0 +1,255 @@
+use std::ffi::CString;
+use std::fs;
+use std::io::Read;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use libc::{c_int, c_ulong};
+
+use super::BootError;
+
+const LOOP_CONTROL_PATH: &str = "/dev/loop-control";
+const LOOP_CTL_GET_FREE: c_ulong = 0x4C82;
+const LOOP_SET_FD: c_ulong = 0x4C00;
+const LOOP_CLR_FD: c_ulong = 0x4C01;
+
+extern "C" {
+    fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+}
+
+/// A single mounted layer of a VM's rootfs, tracked so it can be
+/// unwound on failure and reported back via `agent_vm_status`.
+#[derive(Debug, Clone)]
+pub struct MountedLayer {
+    pub lowerdir: PathBuf,
+    pub upperdir: PathBuf,
+    pub workdir: PathBuf,
+    pub merged: PathBuf,
+    loop_device: Option<PathBuf>,
+}
+
+fn attach_loop_device(image: &Path) -> Result<PathBuf, BootError> {
+    let ctl = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(LOOP_CONTROL_PATH)
+        .map_err(|_| BootError::LoopAttachFailed)?;
+
+    let minor = unsafe { ioctl(ctl.as_raw_fd(), LOOP_CTL_GET_FREE) };
+    if minor < 0 {
+        return Err(BootError::LoopAttachFailed);
+    }
+
+    let dev_path = PathBuf::from(format!("/dev/loop{minor}"));
+    let dev = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&dev_path)
+        .map_err(|_| BootError::LoopAttachFailed)?;
+    let backing = fs::OpenOptions::new()
+        .read(true)
+        .open(image)
+        .map_err(|_| BootError::LoopAttachFailed)?;
+
+    let rc = unsafe { ioctl(dev.as_raw_fd(), LOOP_SET_FD, backing.as_raw_fd()) };
+    if rc < 0 {
+        return Err(BootError::LoopAttachFailed);
+    }
+
+    Ok(dev_path)
+}
+
+fn detach_loop_device(dev_path: &Path) {
+    if let Ok(dev) = fs::OpenOptions::new().read(true).write(true).open(dev_path) {
+        unsafe {
+            ioctl(dev.as_raw_fd(), LOOP_CLR_FD);
+        }
+    }
+}
+
+pub(crate) fn raw_mount(
+    source: &str,
+    target: &Path,
+    fstype: &str,
+    flags: c_ulong,
+    data: Option<&str>,
+) -> Result<(), ()> {
+    let source = CString::new(source).map_err(|_| ())?;
+    let target = CString::new(target.to_string_lossy().as_bytes()).map_err(|_| ())?;
+    let fstype = CString::new(fstype).map_err(|_| ())?;
+    let data = data.map(CString::new).transpose().map_err(|_| ())?;
+
+    let rc = unsafe {
+        libc::mount(
+            source.as_ptr(),
+            target.as_ptr(),
+            fstype.as_ptr(),
+            flags,
+            data.as_ref()
+                .map(|d| d.as_ptr() as *const libc::c_void)
+                .unwrap_or(ptr::null()),
+        )
+    };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Unwinds whatever prefix of `mount_overlay_rootfs`'s stages completed
+/// before a later stage failed: the upperdir tmpfs (if mounted), the
+/// lowerdir, and the loop device, in that order, best-effort.
+fn unwind_partial_overlay(loop_device: &Path, lowerdir: &Path, upperdir: &Path, mounted_upperdir: bool) {
+    if mounted_upperdir {
+        unsafe {
+            libc::umount2(
+                CString::new(upperdir.to_string_lossy().as_bytes()).unwrap().as_ptr(),
+                libc::MNT_DETACH,
+            );
+        }
+    }
+    unsafe {
+        libc::umount2(
+            CString::new(lowerdir.to_string_lossy().as_bytes()).unwrap().as_ptr(),
+            libc::MNT_DETACH,
+        );
+    }
+    detach_loop_device(loop_device);
+}
+
+/// Loop-mounts `rootfs_image` read-only, lays down a tmpfs-backed
+/// upperdir+workdir sized to `memory_mib`, and mounts an `overlay`
+/// filesystem merging the two under `work_root`.
+///
+/// Failure at any stage unwinds every stage that already succeeded, so a
+/// caller never has to guess which of the loop device, lowerdir mount, or
+/// upperdir mount was left behind.
+pub fn mount_overlay_rootfs(
+    rootfs_image: &Path,
+    work_root: &Path,
+    memory_mib: u32,
+) -> Result<MountedLayer, BootError> {
+    let lowerdir = work_root.join("lower");
+    let upperdir = work_root.join("upper");
+    let workdir = work_root.join("work");
+    let merged = work_root.join("merged");
+    for dir in [&lowerdir, &upperdir, &workdir, &merged] {
+        fs::create_dir_all(dir).map_err(|_| BootError::OverlayMountFailed)?;
+    }
+
+    let loop_device = attach_loop_device(rootfs_image)?;
+    let fstype = detect_rootfs_fstype(rootfs_image);
+    if raw_mount(
+        loop_device.to_string_lossy().as_ref(),
+        &lowerdir,
+        fstype,
+        libc::MS_RDONLY,
+        None,
+    )
+    .is_err()
+    {
+        detach_loop_device(&loop_device);
+        return Err(BootError::OverlayMountFailed);
+    }
+
+    if raw_mount(
+        "tmpfs",
+        &upperdir,
+        "tmpfs",
+        0,
+        Some(&format!("size={memory_mib}m")),
+    )
+    .is_err()
+    {
+        unwind_partial_overlay(&loop_device, &lowerdir, &upperdir, false);
+        return Err(BootError::OverlayMountFailed);
+    }
+
+    let overlay_opts = format!(
+        "lowerdir={},upperdir={},workdir={}",
+        lowerdir.display(),
+        upperdir.display(),
+        workdir.display(),
+    );
+    if raw_mount("overlay", &merged, "overlay", 0, Some(&overlay_opts)).is_err() {
+        unwind_partial_overlay(&loop_device, &lowerdir, &upperdir, true);
+        return Err(BootError::OverlayMountFailed);
+    }
+
+    Ok(MountedLayer {
+        lowerdir,
+        upperdir,
+        workdir,
+        merged,
+        loop_device: Some(loop_device),
+    })
+}
+
+/// Detects whether `rootfs_image` is squashfs or ext4 by sniffing its
+/// first 4 magic bytes, defaulting to ext4 when unrecognized.
+fn detect_rootfs_fstype(rootfs_image: &Path) -> &'static str {
+    const SQUASHFS_MAGIC: [u8; 4] = [0x68, 0x73, 0x71, 0x73];
+    let mut magic = [0u8; 4];
+    if let Ok(mut file) = fs::File::open(rootfs_image) {
+        if file.read_exact(&mut magic).is_ok() && magic == SQUASHFS_MAGIC {
+            return "squashfs";
+        }
+    }
+    "ext4"
+}
+
+/// The deterministic host-visible paths for a VM's overlay layers under
+/// `work_root`, without a loop device attached — used to report a booted
+/// VM's layout from the host side without needing the boot child to echo
+/// it back.
+pub(crate) fn layer_paths(work_root: &Path) -> MountedLayer {
+    MountedLayer {
+        lowerdir: work_root.join("lower"),
+        upperdir: work_root.join("upper"),
+        workdir: work_root.join("work"),
+        merged: work_root.join("merged"),
+        loop_device: None,
+    }
+}
+
+/// Bind-mounts `/proc`, `/sys`, `/dev` from the host into `merged`.
+pub fn bind_pseudo_filesystems(merged: &Path) -> Result<(), BootError> {
+    for name in ["proc", "sys", "dev"] {
+        let target = merged.join(name);
+        fs::create_dir_all(&target).map_err(|_| BootError::PseudoFsMountFailed)?;
+        raw_mount(&format!("/{name}"), &target, "none", libc::MS_BIND, None)
+            .map_err(|_| BootError::PseudoFsMountFailed)?;
+    }
+    Ok(())
+}
+
+/// Unmounts a set of layers in reverse order, best-effort.
+pub fn unmount_layers(layers: &[MountedLayer]) {
+    for layer in layers.iter().rev() {
+        for name in ["dev", "sys", "proc"] {
+            unsafe {
+                libc::umount2(
+                    CString::new(layer.merged.join(name).to_string_lossy().as_bytes())
+                        .unwrap()
+                        .as_ptr(),
+                    libc::MNT_DETACH,
+                );
+            }
+        }
+        for dir in [&layer.merged, &layer.upperdir, &layer.lowerdir] {
+            unsafe {
+                libc::umount2(
+                    CString::new(dir.to_string_lossy().as_bytes())
+                        .unwrap()
+                        .as_ptr(),
+                    libc::MNT_DETACH,
+                );
+            }
+        }
+        if let Some(dev) = &layer.loop_device {
+            detach_loop_device(dev);
+        }
+    }
+}
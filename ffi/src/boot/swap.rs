@@ -0,0 +1,110 @@
+use std::ffi::CString;
+use std::fs::{self, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// Below this much requested memory, a swapfile is activated inside the
+/// merged rootfs to absorb bursts, mirroring rumia's swap handling. Only
+/// used when a VM's config doesn't specify a [`SwapConfig`] of its own.
+pub const LOW_MEMORY_THRESHOLD_MIB: u32 = 256;
+
+const SWAPFILE_NAME: &str = ".agent-swapfile";
+const DEFAULT_SWAPFILE_SIZE_MIB: u64 = 64;
+const SWAP_MAGIC: &[u8] = b"SWAPSPACE2";
+
+/// An explicit swap request from a VM's `[swap]` TOML section, overriding
+/// the [`LOW_MEMORY_THRESHOLD_MIB`]/default-size heuristic.
+#[derive(Debug, Clone, Copy)]
+pub struct SwapConfig {
+    pub enabled: bool,
+    pub size_mib: u64,
+}
+
+fn swapfile_path(merged: &Path) -> PathBuf {
+    merged.join(SWAPFILE_NAME)
+}
+
+/// Creates and activates a swapfile inside `merged`, sized and gated by
+/// `swap_config` when given, falling back to activating a
+/// [`DEFAULT_SWAPFILE_SIZE_MIB`] swapfile below [`LOW_MEMORY_THRESHOLD_MIB`]
+/// otherwise. Returns whether swap was actually activated.
+///
+/// Swap is a best-effort optimization, not a boot requirement, so any
+/// failure here is swallowed rather than propagated.
+pub fn activate_swap_if_needed(
+    merged: &Path,
+    memory_mib: u32,
+    swap_config: Option<SwapConfig>,
+) -> bool {
+    let (enabled, size_mib) = match swap_config {
+        Some(config) => (config.enabled, config.size_mib),
+        None => (
+            memory_mib < LOW_MEMORY_THRESHOLD_MIB,
+            DEFAULT_SWAPFILE_SIZE_MIB,
+        ),
+    };
+    if !enabled {
+        return false;
+    }
+    try_activate_swap(merged, size_mib).is_ok()
+}
+
+fn try_activate_swap(merged: &Path, size_mib: u64) -> Result<(), ()> {
+    if size_mib == 0 {
+        return Err(());
+    }
+
+    let path = swapfile_path(merged);
+    let size_bytes = size_mib * 1024 * 1024;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .mode(0o600)
+        .open(&path)
+        .map_err(|_| ())?;
+    file.set_len(size_bytes).map_err(|_| ())?;
+    write_swap_header(&mut file, size_bytes)?;
+    drop(file);
+
+    let path_c = CString::new(path.to_string_lossy().as_bytes()).map_err(|_| ())?;
+    let rc = unsafe { libc::swapon(path_c.as_ptr(), 0) };
+    if rc != 0 {
+        let _ = fs::remove_file(&path);
+        return Err(());
+    }
+
+    Ok(())
+}
+
+/// Writes a minimal `mkswap(8)`-equivalent header: a zeroed first page
+/// with the version and last-usable-page fields set, and the
+/// `SWAPSPACE2` magic in its final 10 bytes. Without this, `swapon(2)`
+/// rejects the file with `EINVAL`.
+fn write_swap_header(file: &mut std::fs::File, size_bytes: u64) -> Result<(), ()> {
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+    let last_page = size_bytes / page_size - 1;
+
+    let mut header = vec![0u8; page_size as usize];
+    header[1024..1028].copy_from_slice(&1u32.to_le_bytes());
+    header[1028..1032].copy_from_slice(&(last_page as u32).to_le_bytes());
+    let magic_at = header.len() - SWAP_MAGIC.len();
+    header[magic_at..].copy_from_slice(SWAP_MAGIC);
+
+    file.seek(SeekFrom::Start(0)).map_err(|_| ())?;
+    file.write_all(&header).map_err(|_| ())
+}
+
+/// Deactivates and removes a swapfile previously created by
+/// [`activate_swap_if_needed`], best-effort.
+pub fn deactivate_swap(merged: &Path) {
+    let path = swapfile_path(merged);
+    if let Ok(path_c) = CString::new(path.to_string_lossy().as_bytes()) {
+        unsafe {
+            libc::swapoff(path_c.as_ptr());
+        }
+    }
+    let _ = fs::remove_file(&path);
+}
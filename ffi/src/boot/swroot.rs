@@ -0,0 +1,95 @@
+use std::ffi::CString;
+use std::fs;
+use std::path::Path;
+
+use super::BootError;
+
+/// Unshares the calling thread into a fresh mount namespace so subsequent
+/// mounts are invisible to the rest of the host.
+pub fn unshare_mount_namespace() -> Result<(), BootError> {
+    let rc = unsafe { libc::unshare(libc::CLONE_NEWNS) };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(BootError::NamespaceFailed)
+    }
+}
+
+/// Moves the process root into `merged` via `pivot_root`, falling back to
+/// `switch_root` semantics (bind-mount merged over `/`, then chroot) when
+/// the kernel rejects `pivot_root` (e.g. because `merged` isn't on its own
+/// mount point, as can happen under some container runtimes).
+pub fn pivot_into_rootfs(merged: &Path) -> Result<(), BootError> {
+    if try_pivot_root(merged) {
+        return Ok(());
+    }
+    switch_root_fallback(merged)
+}
+
+fn try_pivot_root(merged: &Path) -> bool {
+    let old_root = merged.join(".old_root");
+    if fs::create_dir_all(&old_root).is_err() {
+        return false;
+    }
+
+    let new_root = match CString::new(merged.to_string_lossy().as_bytes()) {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    let put_old = match CString::new(old_root.to_string_lossy().as_bytes()) {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+
+    let rc = unsafe {
+        libc::syscall(
+            libc::SYS_pivot_root,
+            new_root.as_ptr(),
+            put_old.as_ptr(),
+        )
+    };
+    if rc != 0 {
+        return false;
+    }
+
+    if std::env::set_current_dir("/").is_err() {
+        return false;
+    }
+
+    let old_root_c = CString::new("/.old_root").unwrap();
+    unsafe {
+        libc::umount2(old_root_c.as_ptr(), libc::MNT_DETACH);
+    }
+    let _ = fs::remove_dir("/.old_root");
+
+    true
+}
+
+fn switch_root_fallback(merged: &Path) -> Result<(), BootError> {
+    let merged_c = CString::new(merged.to_string_lossy().as_bytes())
+        .map_err(|_| BootError::PivotRootFailed)?;
+    let root_c = CString::new("/").map_err(|_| BootError::PivotRootFailed)?;
+
+    let rc = unsafe {
+        libc::mount(
+            merged_c.as_ptr(),
+            root_c.as_ptr(),
+            std::ptr::null(),
+            libc::MS_BIND | libc::MS_REC,
+            std::ptr::null(),
+        )
+    };
+    if rc != 0 {
+        return Err(BootError::PivotRootFailed);
+    }
+
+    let rc = unsafe { libc::chroot(root_c.as_ptr()) };
+    if rc != 0 {
+        return Err(BootError::PivotRootFailed);
+    }
+    if std::env::set_current_dir("/").is_err() {
+        return Err(BootError::PivotRootFailed);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,227 @@
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::log;
+use crate::op::{self, SequenceStep};
+
+/// Failure loading or validating a [`VmSpec`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CfgError {
+    Unreadable,
+    Invalid,
+}
+
+/// A VM definition as read from a TOML file.
+#[derive(Debug, Deserialize)]
+pub struct VmSpec {
+    pub id: String,
+    pub rootfs_image: String,
+    #[serde(default = "default_cpu_count")]
+    pub cpu_count: u32,
+    #[serde(default = "default_memory_mib")]
+    pub memory_mib: u32,
+    #[serde(default = "default_network_mode")]
+    pub network_mode: String,
+    #[serde(default)]
+    pub mounts: Vec<MountSpec>,
+    #[serde(default)]
+    pub swap: Option<SwapSpec>,
+    #[serde(default)]
+    pub boot: Option<BootSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MountSpec {
+    pub source: String,
+    pub target: String,
+    #[serde(default = "default_fstype")]
+    pub fstype: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SwapSpec {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_swap_size_mib")]
+    pub size_mib: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BootSpec {
+    /// Lines in the same mini-DSL as a standalone sequence file (see
+    /// [`crate::op::parse_sequence_text`]).
+    #[serde(default)]
+    pub steps: Vec<String>,
+}
+
+fn default_cpu_count() -> u32 {
+    1
+}
+
+fn default_memory_mib() -> u32 {
+    512
+}
+
+fn default_network_mode() -> String {
+    "none".to_string()
+}
+
+fn default_fstype() -> String {
+    "none".to_string()
+}
+
+fn default_swap_size_mib() -> u64 {
+    64
+}
+
+impl VmSpec {
+    /// Loads and validates a [`VmSpec`] from a TOML file at `path`.
+    ///
+    /// Parse and validation failures are logged with line context (see
+    /// [`crate::log`]) before a [`CfgError`] is returned, since at this
+    /// point no VM handle exists yet to carry diagnostics on.
+    pub fn load(path: &Path) -> Result<VmSpec, CfgError> {
+        let text = fs::read_to_string(path).map_err(|err| {
+            log::push(format!("config {}: unreadable: {err}", path.display()));
+            CfgError::Unreadable
+        })?;
+
+        let spec: VmSpec = toml::from_str(&text).map_err(|err| {
+            log::push(format!("config {}: {err}", path.display()));
+            CfgError::Invalid
+        })?;
+
+        if spec.id.is_empty() {
+            log::push(format!("config {}: `id` must not be empty", path.display()));
+            return Err(CfgError::Invalid);
+        }
+        if spec.rootfs_image.is_empty() {
+            log::push(format!(
+                "config {}: `rootfs_image` must not be empty",
+                path.display()
+            ));
+            return Err(CfgError::Invalid);
+        }
+
+        Ok(spec)
+    }
+
+    /// Flattens `[[mounts]]` and `[boot] steps` into the ordered boot
+    /// sequence [`crate::op::run_sequence`] expects, with explicit mounts
+    /// applied before the boot-sequence steps that may depend on them.
+    pub fn boot_sequence(&self, path: &Path) -> Result<Vec<SequenceStep>, CfgError> {
+        let mut steps: Vec<SequenceStep> = self
+            .mounts
+            .iter()
+            .map(|mount| SequenceStep {
+                step: op::Step::Mount {
+                    source: mount.source.clone(),
+                    target: mount.target.clone(),
+                    fstype: mount.fstype.clone(),
+                },
+                once: false,
+            })
+            .collect();
+
+        if let Some(boot) = &self.boot {
+            let text = boot.steps.join("\n");
+            let parsed = op::parse_sequence_text(&text).map_err(|err| {
+                log::push(format!("config {}: boot.steps: {err:?}", path.display()));
+                CfgError::Invalid
+            })?;
+            steps.extend(parsed);
+        }
+
+        Ok(steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(name: &str, contents: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("agent-ffi-config-test-{name}-{}.toml", std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_minimal_config_with_defaults() {
+        let path = write_config("minimal", "id = \"vm-1\"\nrootfs_image = \"/tmp/rootfs.img\"\n");
+        let spec = VmSpec::load(&path).unwrap();
+        assert_eq!(spec.id, "vm-1");
+        assert_eq!(spec.cpu_count, default_cpu_count());
+        assert_eq!(spec.memory_mib, default_memory_mib());
+        assert_eq!(spec.network_mode, default_network_mode());
+        assert!(spec.mounts.is_empty());
+        assert!(spec.swap.is_none());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_empty_id() {
+        let path = write_config("empty-id", "id = \"\"\nrootfs_image = \"/tmp/rootfs.img\"\n");
+        assert!(matches!(VmSpec::load(&path), Err(CfgError::Invalid)));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_empty_rootfs_image() {
+        let path = write_config("empty-rootfs", "id = \"vm-1\"\nrootfs_image = \"\"\n");
+        assert!(matches!(VmSpec::load(&path), Err(CfgError::Invalid)));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_unparseable_toml() {
+        let path = write_config("garbage", "this is not toml {{{");
+        assert!(matches!(VmSpec::load(&path), Err(CfgError::Invalid)));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_unreadable_path() {
+        assert!(matches!(
+            VmSpec::load(Path::new("/nonexistent/agent-vm-config.toml")),
+            Err(CfgError::Unreadable)
+        ));
+    }
+
+    #[test]
+    fn boot_sequence_orders_mounts_before_boot_steps() {
+        let path = write_config(
+            "boot-sequence",
+            "id = \"vm-1\"\n\
+             rootfs_image = \"/tmp/rootfs.img\"\n\
+             [[mounts]]\n\
+             source = \"/data\"\n\
+             target = \"/mnt/data\"\n\
+             [boot]\n\
+             steps = [\"mkdir /var/lib/agent\"]\n",
+        );
+        let spec = VmSpec::load(&path).unwrap();
+        let steps = spec.boot_sequence(&path).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert!(matches!(steps[0].step, op::Step::Mount { .. }));
+        assert!(matches!(steps[1].step, op::Step::Mkdir { .. }));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn boot_sequence_rejects_invalid_step_syntax() {
+        let path = write_config(
+            "bad-boot-step",
+            "id = \"vm-1\"\n\
+             rootfs_image = \"/tmp/rootfs.img\"\n\
+             [boot]\n\
+             steps = [\"frobnicate /tmp\"]\n",
+        );
+        let spec = VmSpec::load(&path).unwrap();
+        assert_eq!(spec.boot_sequence(&path), Err(CfgError::Invalid));
+        fs::remove_file(&path).unwrap();
+    }
+}
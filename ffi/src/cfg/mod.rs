@@ -0,0 +1,10 @@
+//! TOML-file VM definitions.
+//!
+//! Following rumia's layered config loader (`cfg/config.rs`, `cfg/mod.rs`),
+//! this lets operators describe a whole VM — mounts, swap, and boot
+//! sequence included — declaratively instead of marshalling every field
+//! as raw C pointers through [`crate::vm::AgentVMConfig`].
+
+mod config;
+
+pub use config::{BootSpec, CfgError, MountSpec, SwapSpec, VmSpec};
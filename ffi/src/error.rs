@@ -0,0 +1,150 @@
+use std::os::raw::c_int;
+
+use crate::boot::BootError;
+use crate::cfg::CfgError;
+use crate::op::OpError;
+
+/// Stable, ABI-safe status/error codes returned across the FFI boundary.
+///
+/// Every fallible `agent_*` entry point returns one of these values (as a
+/// plain `c_int`) instead of panicking or trusting caller-supplied state,
+/// mirroring the `error_mapping` approach used by `rust-url-capi`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgentError {
+    Ok = 0,
+    NullPtr = -1,
+    InvalidUtf8 = -2,
+    IdConflict = -3,
+    LaunchFailed = -4,
+    NotFound = -5,
+    NamespaceFailed = -6,
+    LoopAttachFailed = -7,
+    OverlayMountFailed = -8,
+    PseudoFsMountFailed = -9,
+    SwapActivationFailed = -10,
+    PivotRootFailed = -11,
+    SequenceFileUnreadable = -12,
+    SequenceFileInvalid = -13,
+    BootSequenceStepFailed = -14,
+    ConfigUnreadable = -15,
+    ConfigInvalid = -16,
+    ConsoleAlreadyOpen = -17,
+    ConsoleBindFailed = -18,
+    ConsoleNotOpen = -19,
+}
+
+impl AgentError {
+    /// Returns the stable `c_int` value callers should switch on.
+    pub fn code(self) -> c_int {
+        self as c_int
+    }
+}
+
+impl From<AgentError> for c_int {
+    fn from(err: AgentError) -> c_int {
+        err.code()
+    }
+}
+
+impl From<BootError> for AgentError {
+    fn from(err: BootError) -> AgentError {
+        match err {
+            BootError::NamespaceFailed => AgentError::NamespaceFailed,
+            BootError::LoopAttachFailed => AgentError::LoopAttachFailed,
+            BootError::OverlayMountFailed => AgentError::OverlayMountFailed,
+            BootError::PseudoFsMountFailed => AgentError::PseudoFsMountFailed,
+            BootError::PivotRootFailed => AgentError::PivotRootFailed,
+            BootError::ChildDied => AgentError::LaunchFailed,
+        }
+    }
+}
+
+impl From<OpError> for AgentError {
+    fn from(err: OpError) -> AgentError {
+        match err {
+            OpError::SequenceFileUnreadable => AgentError::SequenceFileUnreadable,
+            OpError::SequenceFileInvalid => AgentError::SequenceFileInvalid,
+            OpError::StepFailed { .. } => AgentError::BootSequenceStepFailed,
+        }
+    }
+}
+
+impl From<CfgError> for AgentError {
+    fn from(err: CfgError) -> AgentError {
+        match err {
+            CfgError::Unreadable => AgentError::ConfigUnreadable,
+            CfgError::Invalid => AgentError::ConfigInvalid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_is_the_zero_code() {
+        assert_eq!(AgentError::Ok.code(), 0);
+    }
+
+    #[test]
+    fn every_error_code_is_negative() {
+        let codes = [
+            AgentError::NullPtr,
+            AgentError::InvalidUtf8,
+            AgentError::IdConflict,
+            AgentError::LaunchFailed,
+            AgentError::NotFound,
+            AgentError::NamespaceFailed,
+            AgentError::LoopAttachFailed,
+            AgentError::OverlayMountFailed,
+            AgentError::PseudoFsMountFailed,
+            AgentError::SwapActivationFailed,
+            AgentError::PivotRootFailed,
+            AgentError::SequenceFileUnreadable,
+            AgentError::SequenceFileInvalid,
+            AgentError::BootSequenceStepFailed,
+            AgentError::ConfigUnreadable,
+            AgentError::ConfigInvalid,
+            AgentError::ConsoleAlreadyOpen,
+            AgentError::ConsoleBindFailed,
+            AgentError::ConsoleNotOpen,
+        ];
+        for code in codes {
+            assert!(code.code() < 0, "{code:?} should be a negative code");
+        }
+    }
+
+    #[test]
+    fn boot_error_maps_one_to_one_except_child_died() {
+        assert_eq!(AgentError::from(BootError::NamespaceFailed), AgentError::NamespaceFailed);
+        assert_eq!(AgentError::from(BootError::LoopAttachFailed), AgentError::LoopAttachFailed);
+        assert_eq!(AgentError::from(BootError::OverlayMountFailed), AgentError::OverlayMountFailed);
+        assert_eq!(AgentError::from(BootError::PseudoFsMountFailed), AgentError::PseudoFsMountFailed);
+        assert_eq!(AgentError::from(BootError::PivotRootFailed), AgentError::PivotRootFailed);
+        assert_eq!(AgentError::from(BootError::ChildDied), AgentError::LaunchFailed);
+    }
+
+    #[test]
+    fn op_error_maps_to_stable_codes() {
+        assert_eq!(
+            AgentError::from(OpError::SequenceFileUnreadable),
+            AgentError::SequenceFileUnreadable
+        );
+        assert_eq!(
+            AgentError::from(OpError::SequenceFileInvalid),
+            AgentError::SequenceFileInvalid
+        );
+        assert_eq!(
+            AgentError::from(OpError::StepFailed { index: 3 }),
+            AgentError::BootSequenceStepFailed
+        );
+    }
+
+    #[test]
+    fn cfg_error_maps_to_stable_codes() {
+        assert_eq!(AgentError::from(CfgError::Unreadable), AgentError::ConfigUnreadable);
+        assert_eq!(AgentError::from(CfgError::Invalid), AgentError::ConfigInvalid);
+    }
+}
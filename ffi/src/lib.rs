@@ -0,0 +1,12 @@
+// FFI entry points necessarily take raw pointers from the C side without
+// themselves being `unsafe fn` (the `extern "C"` ABI callers expect); each
+// one validates its pointers before dereferencing instead.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+pub mod boot;
+pub mod cfg;
+pub mod error;
+pub mod log;
+pub mod op;
+pub mod repl;
+pub mod vm;
@@ -0,0 +1,33 @@
+//! A small in-memory ring buffer of diagnostic lines.
+//!
+//! Used for context that has nowhere else to live yet at the point it's
+//! produced — e.g. a config parse failure happens before any VM handle
+//! exists to carry it. Surfaced later by the management console's `logs`
+//! command.
+
+use std::sync::{Mutex, OnceLock};
+
+const MAX_LINES: usize = 1000;
+
+fn buffer() -> &'static Mutex<Vec<String>> {
+    static BUFFER: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+    BUFFER.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Appends a line to the log buffer, trimming the oldest entries once
+/// [`MAX_LINES`] is exceeded.
+pub fn push(line: impl Into<String>) {
+    let mut buf = buffer().lock().unwrap();
+    buf.push(line.into());
+    if buf.len() > MAX_LINES {
+        let overflow = buf.len() - MAX_LINES;
+        buf.drain(0..overflow);
+    }
+}
+
+/// Returns up to the `limit` most recent log lines, oldest first.
+pub fn recent(limit: usize) -> Vec<String> {
+    let buf = buffer().lock().unwrap();
+    let start = buf.len().saturating_sub(limit);
+    buf[start..].to_vec()
+}
@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::Path;
+
+use super::Step;
+
+/// Checks whether a step's desired state already holds, so
+/// [`super::run_sequence`] can skip steps that were already applied by an
+/// earlier launch.
+pub fn already_satisfied(step: &Step) -> bool {
+    match step {
+        Step::Mount { target, .. } => is_mountpoint(Path::new(target)),
+        Step::Exec { .. } => false,
+        Step::Symlink { target, link } => fs::read_link(link)
+            .map(|existing| existing == Path::new(target))
+            .unwrap_or(false),
+        Step::Mkdir { path } => Path::new(path).is_dir(),
+        Step::WriteFile { path, content } => fs::read(path)
+            .map(|existing| existing == content.as_bytes())
+            .unwrap_or(false),
+    }
+}
+
+/// Reports whether `target` is itself a mountpoint, by checking whether
+/// it and its parent live on different devices (the same heuristic
+/// `mountpoint(1)` uses).
+fn is_mountpoint(target: &Path) -> bool {
+    let (Ok(target_meta), Some(parent)) = (fs::metadata(target), target.parent()) else {
+        return false;
+    };
+    match fs::metadata(parent) {
+        Ok(parent_meta) => {
+            use std::os::unix::fs::MetadataExt;
+            target_meta.dev() != parent_meta.dev()
+        }
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("agent-ffi-makesure-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn exec_is_never_already_satisfied() {
+        assert!(!already_satisfied(&Step::Exec {
+            command: "true".to_string(),
+            args: vec![],
+        }));
+    }
+
+    #[test]
+    fn mkdir_is_satisfied_once_the_directory_exists() {
+        let dir = scratch_dir("mkdir");
+        let target = dir.join("child");
+        let step = Step::Mkdir {
+            path: target.to_string_lossy().to_string(),
+        };
+        assert!(!already_satisfied(&step));
+        fs::create_dir_all(&target).unwrap();
+        assert!(already_satisfied(&step));
+    }
+
+    #[test]
+    fn write_file_is_satisfied_only_with_matching_content() {
+        let dir = scratch_dir("write-file");
+        let path = dir.join("content.txt");
+        let step = Step::WriteFile {
+            path: path.to_string_lossy().to_string(),
+            content: "hello".to_string(),
+        };
+        assert!(!already_satisfied(&step));
+        fs::write(&path, "something else").unwrap();
+        assert!(!already_satisfied(&step));
+        fs::write(&path, "hello").unwrap();
+        assert!(already_satisfied(&step));
+    }
+
+    #[test]
+    fn symlink_is_satisfied_only_when_pointing_at_the_right_target() {
+        let dir = scratch_dir("symlink");
+        let link = dir.join("link");
+        let step = Step::Symlink {
+            target: "/etc/hostname".to_string(),
+            link: link.to_string_lossy().to_string(),
+        };
+        assert!(!already_satisfied(&step));
+        std::os::unix::fs::symlink("/etc/other", &link).unwrap();
+        assert!(!already_satisfied(&step));
+        fs::remove_file(&link).unwrap();
+        std::os::unix::fs::symlink("/etc/hostname", &link).unwrap();
+        assert!(already_satisfied(&step));
+    }
+}
@@ -0,0 +1,81 @@
+//! Declarative, idempotent boot-sequence runner.
+//!
+//! Modeled on rumia's `op/seq.rs`, `op/once.rs`, and `op/makesure.rs`: a
+//! VM's boot sequence is an ordered list of [`Step`]s, each checked
+//! against the live filesystem before being applied so re-launching an
+//! already-provisioned VM is safe and convergent.
+
+mod makesure;
+mod once;
+mod seq;
+
+use std::path::Path;
+
+pub use seq::{parse_sequence_file, parse_sequence_text, SequenceStep, Step};
+
+/// Failure of the boot-sequence runner, reported to the FFI boundary
+/// together with the index of the step that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpError {
+    SequenceFileUnreadable,
+    SequenceFileInvalid,
+    StepFailed { index: usize },
+}
+
+/// Runs `steps` in order against the current root filesystem (the caller
+/// is expected to have already `pivot_root`ed into the VM's rootfs).
+///
+/// Each step is skipped if its "makesure" predicate reports the desired
+/// state already holds. Steps marked `once` additionally record a marker
+/// under `/.agent-once` and are skipped while that marker exists; the
+/// marker lives in the VM's tmpfs-backed upperdir, which is mounted fresh
+/// on every launch, so it does not survive a relaunch. Execution
+/// stops at the first non-recoverable failure; the index of that step is
+/// carried in [`OpError::StepFailed`] so callers can resume from there.
+pub fn run_sequence(steps: &[SequenceStep]) -> Result<(), OpError> {
+    for (index, step) in steps.iter().enumerate() {
+        if step.once && once::has_run(&step.step) {
+            continue;
+        }
+        if makesure::already_satisfied(&step.step) {
+            if step.once {
+                once::mark_run(&step.step);
+            }
+            continue;
+        }
+
+        apply_step(&step.step).map_err(|()| OpError::StepFailed { index })?;
+
+        if step.once {
+            once::mark_run(&step.step);
+        }
+    }
+    Ok(())
+}
+
+fn apply_step(step: &Step) -> Result<(), ()> {
+    match step {
+        Step::Mount {
+            source,
+            target,
+            fstype,
+        } => {
+            std::fs::create_dir_all(Path::new(target)).map_err(|_| ())?;
+            crate::boot::mount::raw_mount(source, Path::new(target), fstype, 0, None)
+        }
+        Step::Exec { command, args } => std::process::Command::new(command)
+            .args(args)
+            .status()
+            .map_err(|_| ())
+            .and_then(|status| if status.success() { Ok(()) } else { Err(()) }),
+        Step::Symlink { target, link } => {
+            let link_path = Path::new(link);
+            if link_path.exists() || link_path.symlink_metadata().is_ok() {
+                std::fs::remove_file(link_path).map_err(|_| ())?;
+            }
+            std::os::unix::fs::symlink(target, link_path).map_err(|_| ())
+        }
+        Step::Mkdir { path } => std::fs::create_dir_all(path).map_err(|_| ()),
+        Step::WriteFile { path, content } => std::fs::write(path, content).map_err(|_| ()),
+    }
+}
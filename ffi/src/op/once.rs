@@ -0,0 +1,32 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use super::Step;
+
+/// Directory (relative to the VM's root) markers for `once` steps live
+/// in. Since this runs after `pivot_root`, this path resolves to the
+/// VM's overlay upperdir transparently. That upperdir is a tmpfs mounted
+/// fresh on each launch, so markers last only for the current boot.
+const ONCE_MARKER_DIR: &str = "/.agent-once";
+
+fn marker_path(step: &Step) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    format!("{step:?}").hash(&mut hasher);
+    Path::new(ONCE_MARKER_DIR).join(format!("{:x}", hasher.finish()))
+}
+
+/// Returns whether `step` already has a recorded once-marker.
+pub fn has_run(step: &Step) -> bool {
+    marker_path(step).exists()
+}
+
+/// Records that `step` has run, so [`has_run`] reports it for the rest
+/// of the current boot.
+pub fn mark_run(step: &Step) {
+    let marker = marker_path(step);
+    if let Some(parent) = marker.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let _ = std::fs::write(marker, b"");
+}
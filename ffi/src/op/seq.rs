@@ -0,0 +1,198 @@
+use std::fs;
+use std::path::Path;
+
+use super::OpError;
+
+/// A single boot-sequence operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Mount {
+        source: String,
+        target: String,
+        fstype: String,
+    },
+    Exec {
+        command: String,
+        args: Vec<String>,
+    },
+    Symlink {
+        target: String,
+        link: String,
+    },
+    Mkdir {
+        path: String,
+    },
+    WriteFile {
+        path: String,
+        content: String,
+    },
+}
+
+/// A [`Step`] plus its "once" flag: `once` steps record a marker and are
+/// skipped while it exists. Markers live in the VM's tmpfs-backed
+/// upperdir, so they do not carry over to a later launch of the same VM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceStep {
+    pub step: Step,
+    pub once: bool,
+}
+
+/// Parses a sequence file into an ordered list of steps.
+///
+/// Each non-blank, non-comment line is one step: a verb followed by
+/// whitespace-separated fields, optionally prefixed with `once:` to mark
+/// it first-boot-only:
+///
+/// ```text
+/// once: mkdir /var/lib/agent
+/// once: write-file /etc/machine-id 0123456789abcdef0123456789abcdef
+/// mount /proc /proc proc
+/// symlink /proc/self/fd /dev/fd
+/// exec /sbin/agent-init --ready
+/// ```
+pub fn parse_sequence_file(path: &Path) -> Result<Vec<SequenceStep>, OpError> {
+    let text = fs::read_to_string(path).map_err(|_| OpError::SequenceFileUnreadable)?;
+    parse_sequence_text(&text)
+}
+
+/// Parses sequence-file syntax directly from a string, e.g. the `[boot]
+/// steps` array of a [`crate::cfg::VmSpec`].
+pub fn parse_sequence_text(text: &str) -> Result<Vec<SequenceStep>, OpError> {
+    let mut steps = Vec::new();
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (once, rest) = match line.strip_prefix("once:") {
+            Some(rest) => (true, rest.trim()),
+            None => (false, line),
+        };
+
+        let mut fields = rest.split_whitespace();
+        let verb = fields.next().ok_or(OpError::SequenceFileInvalid)?;
+        let step = match verb {
+            "mount" => Step::Mount {
+                source: fields.next().ok_or(OpError::SequenceFileInvalid)?.to_string(),
+                target: fields.next().ok_or(OpError::SequenceFileInvalid)?.to_string(),
+                fstype: fields.next().ok_or(OpError::SequenceFileInvalid)?.to_string(),
+            },
+            "exec" => {
+                let command = fields.next().ok_or(OpError::SequenceFileInvalid)?.to_string();
+                Step::Exec {
+                    command,
+                    args: fields.map(str::to_string).collect(),
+                }
+            }
+            "symlink" => Step::Symlink {
+                target: fields.next().ok_or(OpError::SequenceFileInvalid)?.to_string(),
+                link: fields.next().ok_or(OpError::SequenceFileInvalid)?.to_string(),
+            },
+            "mkdir" => Step::Mkdir {
+                path: fields.next().ok_or(OpError::SequenceFileInvalid)?.to_string(),
+            },
+            "write-file" => {
+                let path = fields.next().ok_or(OpError::SequenceFileInvalid)?.to_string();
+                let content: Vec<&str> = fields.collect();
+                Step::WriteFile {
+                    path,
+                    content: content.join(" "),
+                }
+            }
+            _ => return Err(OpError::SequenceFileInvalid),
+        };
+
+        steps.push(SequenceStep { step, once });
+    }
+
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_verb() {
+        let steps = parse_sequence_text(
+            "mount /proc /proc proc\n\
+             exec /sbin/agent-init --ready\n\
+             symlink /proc/self/fd /dev/fd\n\
+             mkdir /var/lib/agent\n\
+             write-file /etc/hostname agent-vm\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            steps[0].step,
+            Step::Mount {
+                source: "/proc".to_string(),
+                target: "/proc".to_string(),
+                fstype: "proc".to_string(),
+            }
+        );
+        assert_eq!(
+            steps[1].step,
+            Step::Exec {
+                command: "/sbin/agent-init".to_string(),
+                args: vec!["--ready".to_string()],
+            }
+        );
+        assert_eq!(
+            steps[2].step,
+            Step::Symlink {
+                target: "/proc/self/fd".to_string(),
+                link: "/dev/fd".to_string(),
+            }
+        );
+        assert_eq!(
+            steps[3].step,
+            Step::Mkdir {
+                path: "/var/lib/agent".to_string(),
+            }
+        );
+        assert_eq!(
+            steps[4].step,
+            Step::WriteFile {
+                path: "/etc/hostname".to_string(),
+                content: "agent-vm".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn skips_blank_lines_and_comments() {
+        let steps = parse_sequence_text("\n# a comment\n   \nmkdir /var/lib/agent\n").unwrap();
+        assert_eq!(steps.len(), 1);
+    }
+
+    #[test]
+    fn once_prefix_sets_the_flag() {
+        let steps = parse_sequence_text("once: mkdir /var/lib/agent\nmkdir /tmp/x\n").unwrap();
+        assert!(steps[0].once);
+        assert!(!steps[1].once);
+    }
+
+    #[test]
+    fn unknown_verb_is_invalid() {
+        assert_eq!(
+            parse_sequence_text("frobnicate /tmp"),
+            Err(OpError::SequenceFileInvalid)
+        );
+    }
+
+    #[test]
+    fn missing_fields_are_invalid() {
+        assert_eq!(parse_sequence_text("mount /proc /proc"), Err(OpError::SequenceFileInvalid));
+        assert_eq!(parse_sequence_text("symlink /only-one"), Err(OpError::SequenceFileInvalid));
+    }
+
+    #[test]
+    fn unreadable_file_is_reported() {
+        assert_eq!(
+            parse_sequence_file(Path::new("/nonexistent/agent-vm-sequence-file")),
+            Err(OpError::SequenceFileUnreadable)
+        );
+    }
+}
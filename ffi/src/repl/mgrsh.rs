@@ -0,0 +1,63 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::vm::{self, AgentVM};
+
+/// Accepts connections until `running` is cleared, serving each one in
+/// turn (one client at a time is enough for an operator/diagnostic
+/// console). Each connection is published to `active_stream` for as long
+/// as it's being served, so [`super::Console::close`] can shut a stuck
+/// one down instead of waiting for it to finish on its own.
+pub(super) fn serve(
+    listener: UnixListener,
+    running: &Arc<AtomicBool>,
+    active_stream: &Arc<Mutex<Option<UnixStream>>>,
+    vm_addr: usize,
+) {
+    for conn in listener.incoming() {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+        if let Ok(stream) = conn {
+            handle_connection(stream, active_stream, vm_addr);
+        }
+    }
+}
+
+fn handle_connection(stream: UnixStream, active_stream: &Arc<Mutex<Option<UnixStream>>>, vm_addr: usize) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    if let Ok(registered) = stream.try_clone() {
+        *active_stream.lock().unwrap() = Some(registered);
+    }
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        let response = dispatch(&line, vm_addr);
+        if writeln!(writer, "{response}").is_err() {
+            break;
+        }
+    }
+    *active_stream.lock().unwrap() = None;
+}
+
+fn dispatch(line: &str, vm_addr: usize) -> String {
+    let handle = vm_addr as *mut AgentVM;
+    let mut words = line.trim().splitn(2, ' ');
+    let command = words.next().unwrap_or("");
+    let rest = words.next().unwrap_or("").trim();
+
+    match command {
+        "status" => vm::console_status(handle),
+        "stop" => vm::console_stop(handle),
+        "cleanup" => vm::console_cleanup(handle),
+        "mounts" => vm::console_mounts(handle),
+        "logs" => crate::log::recent(100).join("\n"),
+        "exec" => vm::console_exec(handle, rest),
+        "" => "{\"error\":\"empty command\"}".to_string(),
+        other => format!("{{\"error\":\"unknown command: {other}\"}}"),
+    }
+}
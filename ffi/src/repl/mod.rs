@@ -0,0 +1,81 @@
+//! Unix-socket management console for a running VM.
+//!
+//! Inspired by rumia's `repl/mgrsh.rs` and `repl/mod.rs`: a line-oriented
+//! command shell served over a Unix domain socket, dispatching to the
+//! same internal operations the FFI functions use so both humans and
+//! supervising processes can drive a VM live.
+
+mod mgrsh;
+
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use crate::vm::AgentVM;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleError {
+    BindFailed,
+}
+
+/// An open console: the listener thread plus what's needed to tear it
+/// down cleanly.
+pub struct Console {
+    socket_path: PathBuf,
+    running: Arc<AtomicBool>,
+    /// A clone of whichever client connection the worker thread is
+    /// currently serving, if any, so [`close`](Console::close) can shut
+    /// it down instead of waiting on a client that may never send
+    /// another line or disconnect.
+    active_stream: Arc<Mutex<Option<UnixStream>>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Console {
+    /// Binds `socket_path` and starts serving commands against `vm` on a
+    /// background thread.
+    pub fn open(vm: *mut AgentVM, socket_path: PathBuf) -> Result<Console, ConsoleError> {
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).map_err(|_| ConsoleError::BindFailed)?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let running_thread = Arc::clone(&running);
+        let active_stream = Arc::new(Mutex::new(None));
+        let active_stream_thread = Arc::clone(&active_stream);
+        // Carried as a plain address: AgentVM is only ever touched through
+        // the same raw-pointer + registry discipline the other agent_*
+        // entry points already use.
+        let vm_addr = vm as usize;
+        let worker = std::thread::spawn(move || {
+            mgrsh::serve(listener, &running_thread, &active_stream_thread, vm_addr);
+        });
+
+        Ok(Console {
+            socket_path,
+            running,
+            active_stream,
+            worker: Some(worker),
+        })
+    }
+
+    /// Stops serving, joins the listener thread, and unlinks the socket
+    /// file.
+    ///
+    /// A client already connected and blocked reading (or never sending
+    /// another line) would otherwise wedge `worker.join()` forever, so
+    /// any live connection is shut down first, alongside the dummy dial-in
+    /// that unblocks a worker still parked in `accept()`.
+    pub fn close(mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(stream) = self.active_stream.lock().unwrap().take() {
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+        }
+        let _ = UnixStream::connect(&self.socket_path);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        let _ = std::fs::remove_file(&self.socket_path);
+    }
+}
@@ -1,5 +1,15 @@
-use std::ffi::CStr;
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_int};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::boot::{self, BootState};
+use crate::error::AgentError;
+use crate::op::{self, OpError};
+use crate::repl::Console;
 
 #[repr(C)]
 pub struct AgentVMConfig {
@@ -8,46 +18,642 @@ pub struct AgentVMConfig {
     pub cpu_count: u32,
     pub memory_mib: u32,
     pub network_mode: *const c_char,
+    /// Optional path to a boot-sequence file (see [`crate::op`]) run once
+    /// inside the new rootfs after pivot. May be null to skip it.
+    pub boot_sequence_file: *const c_char,
+}
+
+/// Lifecycle phase of a launched VM, reported by [`agent_vm_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VmPhase {
+    Running = 0,
+    Stopped = 1,
+    CleanedUp = 2,
+}
+
+impl VmPhase {
+    fn as_str(self) -> &'static str {
+        match self {
+            VmPhase::Running => "running",
+            VmPhase::Stopped => "stopped",
+            VmPhase::CleanedUp => "cleaned_up",
+        }
+    }
+
+    fn from_u8(value: u8) -> VmPhase {
+        match value {
+            0 => VmPhase::Running,
+            1 => VmPhase::Stopped,
+            _ => VmPhase::CleanedUp,
+        }
+    }
+}
+
+/// A VM's lifecycle phase, shared between the thread an embedding app
+/// calls `agent_stop_vm`/`agent_cleanup_vm` from and the console's
+/// background thread, which can trigger the same transitions concurrently.
+struct AtomicPhase(AtomicU8);
+
+impl AtomicPhase {
+    fn new(phase: VmPhase) -> AtomicPhase {
+        AtomicPhase(AtomicU8::new(phase as u8))
+    }
+
+    fn get(&self) -> VmPhase {
+        VmPhase::from_u8(self.0.load(Ordering::SeqCst))
+    }
+
+    fn set(&self, phase: VmPhase) {
+        self.0.store(phase as u8, Ordering::SeqCst);
+    }
+}
+
+/// An opaque, owned handle to a launched microVM.
+///
+/// Callers never inspect the fields of this type directly; they only ever
+/// hold the `*mut AgentVM` returned by [`agent_launch_vm`] and pass it back
+/// into the other `agent_*` entry points, releasing it with
+/// [`agent_vm_free`] once done.
+pub struct AgentVM {
+    pub(crate) id: String,
+    pub(crate) rootfs_image: String,
+    pub(crate) cpu_count: u32,
+    pub(crate) memory_mib: u32,
+    pub(crate) network_mode: String,
+    pub(crate) boot_state: BootState,
+    phase: AtomicPhase,
+    /// Guards the console against concurrent opens/closes from
+    /// `agent_vm_open_console`, `agent_vm_close_console` and
+    /// `agent_vm_free` called on different threads.
+    pub(crate) console: Mutex<Option<Console>>,
+}
+
+fn json_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    for ch in value.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
 }
 
+impl AgentVM {
+    /// Serializes this VM's live state to a JSON string for
+    /// [`agent_vm_status`].
+    fn status_json(&self) -> String {
+        let layers: Vec<String> = self
+            .boot_state
+            .layers
+            .iter()
+            .map(|layer| {
+                format!(
+                    "{{\"lowerdir\":\"{}\",\"upperdir\":\"{}\",\"workdir\":\"{}\",\"merged\":\"{}\"}}",
+                    json_escape(&layer.lowerdir.to_string_lossy()),
+                    json_escape(&layer.upperdir.to_string_lossy()),
+                    json_escape(&layer.workdir.to_string_lossy()),
+                    json_escape(&layer.merged.to_string_lossy()),
+                )
+            })
+            .collect();
+
+        format!(
+            "{{\"id\":\"{}\",\"pid\":{},\"rootfs_image\":\"{}\",\"cpu_count\":{},\"memory_mib\":{},\"network_mode\":\"{}\",\"phase\":\"{}\",\"mounted_layers\":[{}]}}",
+            json_escape(&self.id),
+            self.boot_state.child_pid.unwrap_or(0),
+            json_escape(&self.rootfs_image),
+            self.cpu_count,
+            self.memory_mib,
+            json_escape(&self.network_mode),
+            self.phase.get().as_str(),
+            layers.join(","),
+        )
+    }
+}
+
+/// Root directory under which each VM's mount/overlay workspace is laid
+/// out, keyed by VM id.
+fn work_root_for(id: &str) -> PathBuf {
+    PathBuf::from("/run/agent-vm").join(id)
+}
+
+/// Tracks which handles are currently live, keyed by the handle address
+/// each maps to an `Arc` of the VM it identifies.
+///
+/// Every handle-consuming entry point resolves the raw pointer through
+/// [`get_live`] rather than dereferencing it directly: that clones the
+/// `Arc` while the registry lock is held, so a concurrent
+/// [`agent_vm_free`] can remove the registry entry and drop its own `Arc`
+/// without the `AgentVM` itself being deallocated until every clone
+/// (including one handed to a console's background thread) is dropped.
+/// This turns the double-free/use-after-free window that a bare
+/// `contains_key` check leaves open into a genuinely safe shared
+/// reference.
+fn registry() -> &'static Mutex<HashMap<usize, Arc<AgentVM>>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<usize, Arc<AgentVM>>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn get_live(handle: *mut AgentVM) -> Option<Arc<AgentVM>> {
+    registry().lock().unwrap().get(&(handle as usize)).cloned()
+}
+
+/// Ids with a launch currently in flight (past the id-conflict check but
+/// not yet registered), so concurrent launches can't race each other into
+/// reusing the same id while the long-running [`boot::boot_rootfs`] call
+/// is in progress without serializing unrelated VMs' handle operations
+/// behind it.
+fn reservations() -> &'static Mutex<HashSet<String>> {
+    static RESERVATIONS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    RESERVATIONS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Releases a [`reservations`] entry when a launch finishes, however it
+/// finishes.
+struct ReservationGuard<'a>(&'a str);
+
+impl Drop for ReservationGuard<'_> {
+    fn drop(&mut self) {
+        reservations().lock().unwrap().remove(self.0);
+    }
+}
+
+unsafe fn cstr_to_string(ptr: *const c_char) -> Result<String, AgentError> {
+    if ptr.is_null() {
+        return Err(AgentError::NullPtr);
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(str::to_owned)
+        .map_err(|_| AgentError::InvalidUtf8)
+}
+
+/// Everything [`launch_internal`] needs to bring a VM up, gathered from
+/// either an [`AgentVMConfig`] or a [`crate::cfg::VmSpec`].
+struct LaunchRequest {
+    id: String,
+    rootfs_image: String,
+    cpu_count: u32,
+    memory_mib: u32,
+    network_mode: String,
+    boot_steps: Vec<op::SequenceStep>,
+    swap_config: Option<boot::SwapConfig>,
+}
+
+/// Drives a launch through boot + boot-sequence + registration, shared by
+/// [`agent_launch_vm`] and [`agent_launch_vm_from_file`].
+///
+/// `out_failed_step` is only written when the return code is
+/// [`AgentError::BootSequenceStepFailed`]; it may be null if the caller
+/// doesn't care which step failed.
+fn launch_internal(
+    request: LaunchRequest,
+    out_handle: *mut *mut AgentVM,
+    out_failed_step: *mut usize,
+) -> c_int {
+    let LaunchRequest {
+        id,
+        rootfs_image,
+        cpu_count,
+        memory_mib,
+        network_mode,
+        boot_steps,
+        swap_config,
+    } = request;
+
+    // Only the id-conflict check and reservation need the locks: holding
+    // either across the boot below would serialize every other VM's
+    // handle operations (stop/cleanup/status/free, and any console
+    // thread's lookups) behind this one launch's fork+mount+pivot+boot
+    // sequence.
+    {
+        let reg = registry().lock().unwrap();
+        let mut reserved = reservations().lock().unwrap();
+        if reg.values().any(|vm| vm.id == id) || reserved.contains(&id) {
+            return AgentError::IdConflict.code();
+        }
+        reserved.insert(id.clone());
+    }
+    let _reservation = ReservationGuard(&id);
+
+    let boot_state = match boot::boot_rootfs(
+        std::path::Path::new(&rootfs_image),
+        &work_root_for(&id),
+        memory_mib,
+        swap_config,
+        move || {
+            op::run_sequence(&boot_steps).map_err(|err| match err {
+                OpError::StepFailed { index } => index,
+                OpError::SequenceFileUnreadable | OpError::SequenceFileInvalid => unreachable!(
+                    "boot_steps are already parsed before boot_rootfs forks"
+                ),
+            })
+        },
+    ) {
+        Ok(state) => state,
+        Err(boot::BootFailure::Stage(err)) => return AgentError::from(err).code(),
+        Err(boot::BootFailure::SequenceStep(index)) => {
+            if !out_failed_step.is_null() {
+                unsafe { *out_failed_step = index };
+            }
+            return AgentError::BootSequenceStepFailed.code();
+        }
+    };
+
+    let vm = Arc::new(AgentVM {
+        id: id.clone(),
+        rootfs_image,
+        cpu_count,
+        memory_mib,
+        network_mode,
+        boot_state,
+        phase: AtomicPhase::new(VmPhase::Running),
+        console: Mutex::new(None),
+    });
+    let handle = Arc::as_ptr(&vm) as *mut AgentVM;
+    registry().lock().unwrap().insert(handle as usize, vm);
+
+    unsafe {
+        *out_handle = handle;
+    }
+    AgentError::Ok.code()
+}
+
+/// Launches a VM from `config`, writing the new opaque handle to
+/// `*out_handle` on success.
+///
+/// Returns an [`AgentError`] code (as `c_int`); `*out_handle` is only
+/// written on [`AgentError::Ok`]. If the boot sequence itself fails
+/// (`AgentError::BootSequenceStepFailed`), the index of the failing step
+/// is written to `*out_failed_step` instead, so callers can fix up and
+/// resume from that step. `out_failed_step` may be null.
 #[no_mangle]
-pub extern "C" fn agent_launch_vm(config: *const AgentVMConfig) -> c_int {
-    if config.is_null() {
-        return -1;
+pub extern "C" fn agent_launch_vm(
+    config: *const AgentVMConfig,
+    out_handle: *mut *mut AgentVM,
+    out_failed_step: *mut usize,
+) -> c_int {
+    if config.is_null() || out_handle.is_null() {
+        return AgentError::NullPtr.code();
     }
 
-    // Validate the id pointer is not null for basic safety.
     let vm_config = unsafe { &*config };
-    if vm_config.id.is_null() {
-        return -1;
+
+    let id = match unsafe { cstr_to_string(vm_config.id) } {
+        Ok(id) if !id.is_empty() => id,
+        Ok(_) => return AgentError::NullPtr.code(),
+        Err(err) => return err.code(),
+    };
+
+    let rootfs_image = match unsafe { cstr_to_string(vm_config.rootfs_image) } {
+        Ok(path) => path,
+        Err(err) => return err.code(),
+    };
+
+    let network_mode = match unsafe { cstr_to_string(vm_config.network_mode) } {
+        Ok(mode) => mode,
+        Err(err) => return err.code(),
+    };
+
+    let boot_steps = if vm_config.boot_sequence_file.is_null() {
+        Vec::new()
+    } else {
+        match unsafe { cstr_to_string(vm_config.boot_sequence_file) }
+            .and_then(|path| op::parse_sequence_file(std::path::Path::new(&path)).map_err(AgentError::from))
+        {
+            Ok(steps) => steps,
+            Err(err) => return err.code(),
+        }
+    };
+
+    launch_internal(
+        LaunchRequest {
+            id,
+            rootfs_image,
+            cpu_count: vm_config.cpu_count,
+            memory_mib: vm_config.memory_mib,
+            network_mode,
+            boot_steps,
+            swap_config: None,
+        },
+        out_handle,
+        out_failed_step,
+    )
+}
+
+/// Loads a [`crate::cfg::VmSpec`] from the TOML file at `path` and
+/// launches it the same way [`agent_launch_vm`] would.
+///
+/// Returns an [`AgentError`] code (as `c_int`); `*out_handle` is only
+/// written on [`AgentError::Ok`]. See [`agent_launch_vm`] for
+/// `out_failed_step`.
+#[no_mangle]
+pub extern "C" fn agent_launch_vm_from_file(
+    path: *const c_char,
+    out_handle: *mut *mut AgentVM,
+    out_failed_step: *mut usize,
+) -> c_int {
+    if path.is_null() || out_handle.is_null() {
+        return AgentError::NullPtr.code();
+    }
+
+    let path = match unsafe { cstr_to_string(path) } {
+        Ok(path) => path,
+        Err(err) => return err.code(),
+    };
+    let path = std::path::Path::new(&path);
+
+    let spec = match crate::cfg::VmSpec::load(path) {
+        Ok(spec) => spec,
+        Err(err) => return AgentError::from(err).code(),
+    };
+
+    let boot_steps = match spec.boot_sequence(path) {
+        Ok(steps) => steps,
+        Err(err) => return AgentError::from(err).code(),
+    };
+    let swap_config = spec.swap.map(|swap| boot::SwapConfig {
+        enabled: swap.enabled,
+        size_mib: swap.size_mib,
+    });
+
+    launch_internal(
+        LaunchRequest {
+            id: spec.id,
+            rootfs_image: spec.rootfs_image,
+            cpu_count: spec.cpu_count,
+            memory_mib: spec.memory_mib,
+            network_mode: spec.network_mode,
+            boot_steps,
+            swap_config,
+        },
+        out_handle,
+        out_failed_step,
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn agent_stop_vm(handle: *mut AgentVM) -> c_int {
+    if handle.is_null() {
+        return AgentError::NullPtr.code();
     }
+    let Some(vm) = get_live(handle) else {
+        return AgentError::NotFound.code();
+    };
+    boot::teardown(&vm.boot_state);
+    vm.phase.set(VmPhase::Stopped);
+    AgentError::Ok.code()
+}
 
-    // Accessing the string ensures it is valid UTF-8, otherwise return error.
-    if unsafe { CStr::from_ptr(vm_config.id) }.to_bytes().is_empty() {
-        return -1;
+#[no_mangle]
+pub extern "C" fn agent_cleanup_vm(handle: *mut AgentVM) -> c_int {
+    if handle.is_null() {
+        return AgentError::NullPtr.code();
     }
+    let Some(vm) = get_live(handle) else {
+        return AgentError::NotFound.code();
+    };
+    boot::teardown(&vm.boot_state);
+    vm.phase.set(VmPhase::CleanedUp);
+    AgentError::Ok.code()
+}
+
+/// Serializes a launched VM's live state (id, pid, cpu/memory config,
+/// network mode, lifecycle phase, mounted layers) to a JSON string.
+///
+/// Returns null for a null or unrecognized handle. The returned string is
+/// heap-allocated via [`CString::into_raw`] and must be released with
+/// [`agent_vm_string_free`].
+#[no_mangle]
+pub extern "C" fn agent_vm_status(handle: *mut AgentVM) -> *mut c_char {
+    if handle.is_null() {
+        return ptr::null_mut();
+    }
+    let Some(vm) = get_live(handle) else {
+        return ptr::null_mut();
+    };
+    match CString::new(vm.status_json()) {
+        Ok(json) => json.into_raw(),
+        Err(_) => ptr::null_mut(),
+    }
+}
 
-    0
+/// Releases a string previously returned by [`agent_vm_status`].
+#[no_mangle]
+pub extern "C" fn agent_vm_string_free(s: *mut c_char) {
+    if s.is_null() {
+        return;
+    }
+    unsafe {
+        drop(CString::from_raw(s));
+    }
 }
 
+/// Opens an interactive management console for `handle`, bound to a Unix
+/// domain socket at `socket_path`. Accepts `status`, `stop`, `cleanup`,
+/// `exec <cmd>`, `mounts`, and `logs` commands, one per line. Each is
+/// answered with one line of text/JSON, except `logs`, which answers with
+/// up to the 100 most recent log lines.
 #[no_mangle]
-pub extern "C" fn agent_stop_vm(vm_id: *const c_char) -> c_int {
-    if vm_id.is_null() {
-        return -1;
+pub extern "C" fn agent_vm_open_console(
+    handle: *mut AgentVM,
+    socket_path: *const c_char,
+) -> c_int {
+    if handle.is_null() {
+        return AgentError::NotFound.code();
+    }
+    let Some(vm) = get_live(handle) else {
+        return AgentError::NotFound.code();
+    };
+    let path = match unsafe { cstr_to_string(socket_path) } {
+        Ok(path) => path,
+        Err(err) => return err.code(),
+    };
+
+    let mut console = vm.console.lock().unwrap();
+    if console.is_some() {
+        return AgentError::ConsoleAlreadyOpen.code();
     }
-    if unsafe { CStr::from_ptr(vm_id) }.to_bytes().is_empty() {
-        return -1;
+
+    match Console::open(handle, PathBuf::from(path)) {
+        Ok(opened) => {
+            *console = Some(opened);
+            AgentError::Ok.code()
+        }
+        Err(_) => AgentError::ConsoleBindFailed.code(),
     }
-    0
 }
 
+/// Tears down a console previously opened with [`agent_vm_open_console`]
+/// and unlinks its socket file.
 #[no_mangle]
-pub extern "C" fn agent_cleanup_vm(vm_id: *const c_char) -> c_int {
-    if vm_id.is_null() {
-        return -1;
+pub extern "C" fn agent_vm_close_console(handle: *mut AgentVM) -> c_int {
+    if handle.is_null() {
+        return AgentError::NotFound.code();
     }
-    if unsafe { CStr::from_ptr(vm_id) }.to_bytes().is_empty() {
-        return -1;
+    let Some(vm) = get_live(handle) else {
+        return AgentError::NotFound.code();
+    };
+    let taken = vm.console.lock().unwrap().take();
+    match taken {
+        Some(console) => {
+            console.close();
+            AgentError::Ok.code()
+        }
+        None => AgentError::ConsoleNotOpen.code(),
+    }
+}
+
+/// Console-facing status lookup; unlike [`agent_vm_status`] this never
+/// returns null, reporting errors inline as JSON instead.
+pub(crate) fn console_status(handle: *mut AgentVM) -> String {
+    match get_live(handle) {
+        Some(vm) => vm.status_json(),
+        None => "{\"error\":\"not found\"}".to_string(),
+    }
+}
+
+pub(crate) fn console_stop(handle: *mut AgentVM) -> String {
+    match agent_stop_vm(handle) {
+        0 => "{\"ok\":true}".to_string(),
+        code => format!("{{\"error\":\"stop failed\",\"code\":{code}}}"),
+    }
+}
+
+pub(crate) fn console_cleanup(handle: *mut AgentVM) -> String {
+    match agent_cleanup_vm(handle) {
+        0 => "{\"ok\":true}".to_string(),
+        code => format!("{{\"error\":\"cleanup failed\",\"code\":{code}}}"),
+    }
+}
+
+pub(crate) fn console_mounts(handle: *mut AgentVM) -> String {
+    let Some(vm) = get_live(handle) else {
+        return "{\"error\":\"not found\"}".to_string();
+    };
+    let layers: Vec<String> = vm
+        .boot_state
+        .layers
+        .iter()
+        .map(|layer| format!("\"{}\"", json_escape(&layer.merged.to_string_lossy())))
+        .collect();
+    format!("[{}]", layers.join(","))
+}
+
+pub(crate) fn console_exec(handle: *mut AgentVM, command_line: &str) -> String {
+    if get_live(handle).is_none() {
+        return "{\"error\":\"not found\"}".to_string();
+    }
+    let mut parts = command_line.split_whitespace();
+    let Some(program) = parts.next() else {
+        return "{\"error\":\"empty command\"}".to_string();
+    };
+    let args: Vec<&str> = parts.collect();
+
+    match std::process::Command::new(program).args(&args).output() {
+        Ok(output) => format!(
+            "{{\"exit_code\":{},\"stdout\":\"{}\",\"stderr\":\"{}\"}}",
+            output.status.code().unwrap_or(-1),
+            json_escape(&String::from_utf8_lossy(&output.stdout)),
+            json_escape(&String::from_utf8_lossy(&output.stderr)),
+        ),
+        Err(_) => "{\"error\":\"exec failed\"}".to_string(),
+    }
+}
+
+/// Releases a handle obtained from [`agent_launch_vm`].
+///
+/// Freeing a null handle is reported as [`AgentError::NullPtr`], and an
+/// already-freed or unrecognized handle as [`AgentError::NotFound`],
+/// rather than causing undefined behaviour. Any
+/// console still open on the VM is closed first, so its listener thread,
+/// socket fd, and socket file aren't leaked.
+///
+/// Removing the registry's `Arc` here only drops *that* strong reference;
+/// the `AgentVM` itself isn't deallocated until every other clone handed
+/// out by [`get_live`] — including one an in-flight call on another
+/// thread might be holding — is dropped too. That's what makes freeing a
+/// handle concurrently with a call that's already resolved it safe
+/// instead of a use-after-free.
+#[no_mangle]
+pub extern "C" fn agent_vm_free(handle: *mut AgentVM) -> c_int {
+    if handle.is_null() {
+        return AgentError::NullPtr.code();
+    }
+    let Some(vm) = registry().lock().unwrap().remove(&(handle as usize)) else {
+        return AgentError::NotFound.code();
+    };
+
+    if let Some(console) = vm.console.lock().unwrap().take() {
+        console.close();
+    }
+    AgentError::Ok.code()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_vm(id: &str) -> AgentVM {
+        AgentVM {
+            id: id.to_string(),
+            rootfs_image: "/tmp/rootfs.img".to_string(),
+            cpu_count: 2,
+            memory_mib: 512,
+            network_mode: "none".to_string(),
+            boot_state: BootState::default(),
+            phase: AtomicPhase::new(VmPhase::Running),
+            console: Mutex::new(None),
+        }
+    }
+
+    #[test]
+    fn json_escape_handles_quotes_backslashes_and_control_chars() {
+        assert_eq!(json_escape("plain"), "plain");
+        assert_eq!(json_escape("a\"b"), "a\\\"b");
+        assert_eq!(json_escape("a\\b"), "a\\\\b");
+        assert_eq!(json_escape("a\nb"), "a\\nb");
+        assert_eq!(json_escape("a\u{0007}b"), "a\\u0007b");
+    }
+
+    #[test]
+    fn status_json_reports_phase_transitions() {
+        let vm = test_vm("vm-1");
+        assert!(vm.status_json().contains("\"phase\":\"running\""));
+        vm.phase.set(VmPhase::Stopped);
+        assert!(vm.status_json().contains("\"phase\":\"stopped\""));
+        vm.phase.set(VmPhase::CleanedUp);
+        assert!(vm.status_json().contains("\"phase\":\"cleaned_up\""));
+    }
+
+    #[test]
+    fn status_json_escapes_its_string_fields() {
+        let vm = test_vm("vm-\"quoted\"");
+        assert!(vm.status_json().contains("\"id\":\"vm-\\\"quoted\\\"\""));
+    }
+
+    #[test]
+    fn atomic_phase_round_trips_through_u8() {
+        for phase in [VmPhase::Running, VmPhase::Stopped, VmPhase::CleanedUp] {
+            let atomic = AtomicPhase::new(phase);
+            assert_eq!(atomic.get(), phase);
+        }
+    }
+
+    #[test]
+    fn get_live_reflects_registry_insert_and_remove() {
+        let vm = Arc::new(test_vm("vm-registry-test"));
+        let handle = Arc::as_ptr(&vm) as *mut AgentVM;
+        assert!(get_live(handle).is_none());
+
+        registry().lock().unwrap().insert(handle as usize, vm);
+        assert!(get_live(handle).is_some());
+
+        registry().lock().unwrap().remove(&(handle as usize));
+        assert!(get_live(handle).is_none());
     }
-    0
 }